@@ -0,0 +1,245 @@
+// Minimal 1D barcode encoders for shelf labels (Code128 set B and EAN-13),
+// rendered to a grayscale PNG. Kept dependency-light on purpose: only `png`.
+
+// Code128 symbol patterns as bar/space widths (6 elements, 11 modules each).
+// Index = symbol value; 103..105 are START A/B/C.
+const CODE128_PATTERNS: [&str; 106] = [
+  "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213",
+  "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132",
+  "221231", "213212", "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211",
+  "212123", "212321", "232121", "111323", "131123", "131321", "112313", "132113", "132311", "211313",
+  "231113", "231311", "112133", "112331", "132131", "113123", "113321", "133121", "313121", "211331",
+  "231131", "213113", "213311", "213131", "311123", "311321", "331121", "312113", "312311", "332111",
+  "314111", "221411", "431111", "111224", "111422", "121124", "121421", "141122", "141221", "112214",
+  "112412", "122114", "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111",
+  "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+  "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311", "113141",
+  "114131", "311141", "411131", "211412", "211214", "211232",
+];
+const CODE128_START_B: usize = 104;
+const CODE128_STOP: &str = "2331112";
+
+const EAN_L: [&str; 10] = [
+  "0001101", "0011001", "0010011", "0111101", "0100011",
+  "0110001", "0101111", "0111011", "0110111", "0001011",
+];
+// Parity of the left half (L/G) selected by the first digit.
+const EAN_PARITY: [&str; 10] = [
+  "LLLLLL", "LLGLGG", "LLGGLG", "LLGGGL", "LGLLGG",
+  "LGGLLG", "LGGGLL", "LGLGLG", "LGLGGL", "LGGLGL",
+];
+
+const MODULE_PX: usize = 2;
+const BAR_HEIGHT_PX: usize = 100;
+const QUIET_ZONE_MODULES: usize = 10;
+
+fn push_widths(modules: &mut Vec<bool>, widths: &str) {
+  // Widths alternate bar/space, starting with a bar.
+  for (i, ch) in widths.chars().enumerate() {
+    let w = ch.to_digit(10).unwrap_or(0) as usize;
+    for _ in 0..w {
+      modules.push(i % 2 == 0);
+    }
+  }
+}
+
+fn push_bits(modules: &mut Vec<bool>, bits: &str) {
+  for ch in bits.chars() {
+    modules.push(ch == '1');
+  }
+}
+
+fn code128b_values(data: &str) -> Vec<usize> {
+  data.bytes().map(|b| (b - 32) as usize).collect()
+}
+
+// Mod-103 checksum: start value plus each symbol value weighted by its position.
+fn code128_checksum(start: usize, values: &[usize]) -> usize {
+  let weighted: usize = values.iter().enumerate().map(|(i, v)| v * (i + 1)).sum();
+  (start + weighted) % 103
+}
+
+fn encode_code128(data: &str) -> Result<Vec<bool>, String> {
+  if data.is_empty() {
+    return Err("code128 data is empty".to_string());
+  }
+  if data.len() > 80 {
+    return Err("code128 data is too long (max 80 characters)".to_string());
+  }
+  if let Some(bad) = data.chars().find(|c| !(' '..='~').contains(c)) {
+    return Err(format!("code128 only supports printable ASCII (invalid character {:?})", bad));
+  }
+
+  let values = code128b_values(data);
+  let checksum = code128_checksum(CODE128_START_B, &values);
+
+  let mut modules = vec![];
+  push_widths(&mut modules, CODE128_PATTERNS[CODE128_START_B]);
+  for v in values {
+    push_widths(&mut modules, CODE128_PATTERNS[v]);
+  }
+  push_widths(&mut modules, CODE128_PATTERNS[checksum]);
+  push_widths(&mut modules, CODE128_STOP);
+  Ok(modules)
+}
+
+fn ean13_check_digit(digits: &[u32]) -> u32 {
+  let sum: u32 = digits
+    .iter()
+    .take(12)
+    .enumerate()
+    .map(|(i, d)| if i % 2 == 0 { *d } else { d * 3 })
+    .sum();
+  (10 - sum % 10) % 10
+}
+
+fn encode_ean13(data: &str) -> Result<Vec<bool>, String> {
+  if !data.chars().all(|c| c.is_ascii_digit()) || !(12..=13).contains(&data.len()) {
+    return Err("ean13 requires 12 or 13 digits".to_string());
+  }
+  let mut digits: Vec<u32> = data.chars().filter_map(|c| c.to_digit(10)).collect();
+  let check = ean13_check_digit(&digits);
+  if digits.len() == 13 {
+    if digits[12] != check {
+      return Err(format!("ean13 check digit mismatch (expected {})", check));
+    }
+  } else {
+    digits.push(check);
+  }
+
+  let parity = EAN_PARITY[digits[0] as usize].as_bytes();
+  let mut modules = vec![];
+  push_bits(&mut modules, "101");
+  for (i, d) in digits[1..7].iter().enumerate() {
+    let l = EAN_L[*d as usize];
+    if parity[i] == b'G' {
+      // G code = reversed complement of L.
+      let g: String = l.chars().rev().map(|c| if c == '1' { '0' } else { '1' }).collect();
+      push_bits(&mut modules, &g);
+    } else {
+      push_bits(&mut modules, l);
+    }
+  }
+  push_bits(&mut modules, "01010");
+  for d in &digits[7..13] {
+    // R code = complement of L.
+    let r: String = EAN_L[*d as usize].chars().map(|c| if c == '1' { '0' } else { '1' }).collect();
+    push_bits(&mut modules, &r);
+  }
+  push_bits(&mut modules, "101");
+  Ok(modules)
+}
+
+/// Encode `data` for the given symbology into a module sequence (true = bar).
+pub fn encode(symbology: &str, data: &str) -> Result<Vec<bool>, String> {
+  let sym = symbology.trim().to_lowercase().replace(['-', '_'], "");
+  match sym.as_str() {
+    "code128" => encode_code128(data),
+    "ean13" => encode_ean13(data.trim()),
+    _ => Err(format!("unsupported symbology: {} (expected code128 or ean13)", symbology.trim())),
+  }
+}
+
+/// Render a module sequence to an 8-bit grayscale PNG.
+pub fn render_png(modules: &[bool]) -> Result<Vec<u8>, String> {
+  let width = (modules.len() + QUIET_ZONE_MODULES * 2) * MODULE_PX;
+  let mut row = vec![255u8; width];
+  for (i, bar) in modules.iter().enumerate() {
+    if *bar {
+      let x = (i + QUIET_ZONE_MODULES) * MODULE_PX;
+      row[x..x + MODULE_PX].fill(0);
+    }
+  }
+  let mut pixels = Vec::with_capacity(width * BAR_HEIGHT_PX);
+  for _ in 0..BAR_HEIGHT_PX {
+    pixels.extend_from_slice(&row);
+  }
+
+  let mut out = Vec::new();
+  let mut enc = png::Encoder::new(&mut out, width as u32, BAR_HEIGHT_PX as u32);
+  enc.set_color(png::ColorType::Grayscale);
+  enc.set_depth(png::BitDepth::Eight);
+  let mut writer = enc
+    .write_header()
+    .map_err(|e| format!("png header failed: {}", e))?;
+  writer
+    .write_image_data(&pixels)
+    .map_err(|e| format!("png write failed: {}", e))?;
+  writer.finish().map_err(|e| format!("png finish failed: {}", e))?;
+  Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn digits(s: &str) -> Vec<u32> {
+    s.chars().filter_map(|c| c.to_digit(10)).collect()
+  }
+
+  #[test]
+  fn ean13_check_digit_known_vectors() {
+    assert_eq!(ean13_check_digit(&digits("400638133393")), 1);
+    assert_eq!(ean13_check_digit(&digits("590123412345")), 7);
+    assert_eq!(ean13_check_digit(&digits("978020137962")), 4);
+    assert_eq!(ean13_check_digit(&digits("000000000000")), 0);
+  }
+
+  #[test]
+  fn ean13_appends_or_verifies_check_digit() {
+    let from_12 = encode("ean13", "400638133393").unwrap();
+    let from_13 = encode("EAN-13", "4006381333931").unwrap();
+    assert_eq!(from_12, from_13);
+    assert_eq!(from_12.len(), 95);
+    assert!(encode("ean13", "4006381333932").is_err());
+    assert!(encode("ean13", "40063813339").is_err());
+  }
+
+  #[test]
+  fn ean13_full_module_sequence_per_leading_digit() {
+    // Expected bars built from the GS1 L/G/R and first-digit parity tables.
+    let cases = [
+      ("0123456789012", "10100110010010011011110101000110110001010111101010100010010010001110100111001011001101101100101"),
+      ("1234567890128", "10100100110111101001110101100010000101001000101010100100011101001110010110011011011001001000101"),
+      ("2345678901234", "10101111010100011011100100001010111011000100101010111010011100101100110110110010000101011100101"),
+      ("3456789012340", "10101000110110001000010100100010001001000101101010111001011001101101100100001010111001110010101"),
+      ("4006381333931", "10100011010100111010111101111010001001011001101010100001010000101000010111010010000101100110101"),
+      ("5901234123457", "10100010110100111011001100100110111101001110101010110011011011001000010101110010011101000100101"),
+      ("6901234567892", "10100010110100111011001100110110111101010001101010100111010100001000100100100011101001101100101"),
+      ("7612345678900", "10101011110110011001001101000010100011011100101010101000010001001001000111010011100101110010101"),
+      ("8412345678905", "10101000110110011001001101000010011101011000101010101000010001001001000111010011100101001110101"),
+      ("9780201379624", "10101110110001001010011100100110100111001100101010100001010001001110100101000011011001011100101"),
+    ];
+    for (data, expected) in cases {
+      let bits: String = encode("ean13", data)
+        .unwrap()
+        .iter()
+        .map(|b| if *b { '1' } else { '0' })
+        .collect();
+      assert_eq!(bits, expected, "{data}");
+    }
+  }
+
+  #[test]
+  fn code128_checksum_known_vectors() {
+    // PJJ123C: 104 + 48*1 + 42*2 + 42*3 + 17*4 + 18*5 + 19*6 + 35*7 = 879 -> 879 % 103 = 55.
+    assert_eq!(code128_checksum(CODE128_START_B, &code128b_values("PJJ123C")), 55);
+    // "A" (33): 104 + 33 = 137 -> 34.
+    assert_eq!(code128_checksum(CODE128_START_B, &code128b_values("A")), 34);
+  }
+
+  #[test]
+  fn code128_layout() {
+    // START + n symbols + checksum at 11 modules each, then the 13-module STOP.
+    let modules = encode("code128", "PJJ123C").unwrap();
+    assert_eq!(modules.len(), 11 * (7 + 2) + 13);
+    assert!(encode("code128", "").is_err());
+    assert!(encode("code128", "caf\u{e9}").is_err());
+  }
+
+  #[test]
+  fn render_png_has_signature() {
+    let png = render_png(&encode("code128", "A").unwrap()).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+  }
+}
@@ -4,6 +4,8 @@ use serde::Serialize;
 use std::process::Command;
 use base64::Engine;
 
+mod barcode;
+
 #[derive(Serialize)]
 struct PrinterInfo {
   name: String,
@@ -186,32 +188,34 @@ fn print_text(text: String, printer: Option<String>, copies: Option<u32>) -> Res
   }
 }
 
-#[tauri::command]
-fn print_pdf_base64(pdf_base64: String, printer: Option<String>, copies: Option<u32>) -> Result<(), String> {
-  let bytes = base64::engine::general_purpose::STANDARD
-    .decode(pdf_base64.trim())
-    .map_err(|e| format!("base64 decode failed: {}", e))?;
-  if bytes.is_empty() {
-    return Err("empty pdf".to_string());
-  }
+/// Write `bytes` to a temp file with `suffix` and send it to `printer`.
+fn print_file(bytes: &[u8], suffix: &str, printer: Option<String>, copies: Option<u32>) -> Result<(), String> {
   let c = clamp_copies(copies);
   let mut tmp = tempfile::Builder::new()
-    .suffix(".pdf")
+    .suffix(suffix)
     .tempfile()
     .map_err(|e| format!("tempfile failed: {}", e))?;
-  std::io::Write::write_all(&mut tmp, &bytes).map_err(|e| format!("write failed: {}", e))?;
-  let path = tmp.path().to_string_lossy().to_string();
+  std::io::Write::write_all(&mut tmp, bytes).map_err(|e| format!("write failed: {}", e))?;
 
   #[cfg(target_os = "windows")]
   {
-    // Best-effort: rely on default PDF handler supporting PrintTo.
+    // Best-effort: rely on the default handler for this file type supporting PrintTo.
     let p = printer.unwrap_or_default();
     if p.trim().is_empty() {
-      return Err("printer is required on Windows for print_pdf".to_string());
+      return Err("printer is required on Windows for printing".to_string());
     }
+    // Start-Process returns before the handler has opened the file, so it must
+    // outlive this call. Windows never cleans %TEMP% by itself: delete it once the
+    // handler has had time to spool (a file is only left behind if the app exits first).
+    let (_, path) = tmp.keep().map_err(|e| format!("tempfile keep failed: {}", e))?;
+    let cleanup = path.clone();
+    std::thread::spawn(move || {
+      std::thread::sleep(std::time::Duration::from_secs(120));
+      let _ = std::fs::remove_file(cleanup);
+    });
     let script = format!(
       "Start-Process -FilePath \"{}\" -Verb PrintTo -ArgumentList '\"{}\"' -WindowStyle Hidden",
-      path.replace('\"', ""),
+      path.to_string_lossy().replace('\"', ""),
       p.replace('\"', "")
     );
     for _ in 0..c {
@@ -235,7 +239,8 @@ fn print_pdf_base64(pdf_base64: String, printer: Option<String>, copies: Option<
     if c != 1 {
       cmd.args(["-n", &c.to_string()]);
     }
-    let out = cmd.arg(&path).output().map_err(|e| format!("lp failed: {}", e))?;
+    // lp copies the file into the spool before exiting, so the temp file can go.
+    let out = cmd.arg(tmp.path()).output().map_err(|e| format!("lp failed: {}", e))?;
     if !out.status.success() {
       return Err(String::from_utf8_lossy(&out.stderr).trim().to_string());
     }
@@ -243,6 +248,36 @@ fn print_pdf_base64(pdf_base64: String, printer: Option<String>, copies: Option<
   }
 }
 
+#[tauri::command]
+fn print_pdf_base64(pdf_base64: String, printer: Option<String>, copies: Option<u32>) -> Result<(), String> {
+  let bytes = base64::engine::general_purpose::STANDARD
+    .decode(pdf_base64.trim())
+    .map_err(|e| format!("base64 decode failed: {}", e))?;
+  if bytes.is_empty() {
+    return Err("empty pdf".to_string());
+  }
+  print_file(&bytes, ".pdf", printer, copies)
+}
+
+#[tauri::command]
+fn render_barcode(symbology: String, data: String) -> Result<String, String> {
+  let modules = barcode::encode(&symbology, &data)?;
+  let png = barcode::render_png(&modules)?;
+  Ok(base64::engine::general_purpose::STANDARD.encode(png))
+}
+
+#[tauri::command]
+fn print_barcode(
+  symbology: String,
+  data: String,
+  printer: Option<String>,
+  copies: Option<u32>,
+) -> Result<(), String> {
+  let modules = barcode::encode(&symbology, &data)?;
+  let png = barcode::render_png(&modules)?;
+  print_file(&png, ".png", printer, copies)
+}
+
 #[tauri::command]
 fn restart_app(app: tauri::AppHandle) -> Result<(), String> {
   app.request_restart();
@@ -252,7 +287,14 @@ fn restart_app(app: tauri::AppHandle) -> Result<(), String> {
 fn main() {
   tauri::Builder::default()
     .plugin(tauri_plugin_updater::Builder::new().build())
-    .invoke_handler(tauri::generate_handler![list_printers, print_text, print_pdf_base64, restart_app])
+    .invoke_handler(tauri::generate_handler![
+      list_printers,
+      print_text,
+      print_pdf_base64,
+      render_barcode,
+      print_barcode,
+      restart_app
+    ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
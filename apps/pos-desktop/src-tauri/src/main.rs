@@ -1,14 +1,41 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::collections::HashMap;
 use std::fs;
 use std::fs::OpenOptions;
-use std::io::{Read, Write};
-use std::net::{SocketAddr, TcpStream};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
-use std::sync::Mutex;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tauri::Manager;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(2);
+/// Don't health-probe a freshly spawned agent until it had time to bind and migrate.
+const WATCHDOG_STARTUP_GRACE: Duration = Duration::from_secs(20);
+/// Consecutive failed health probes before a running agent is considered hung.
+const WATCHDOG_UNHEALTHY_POLLS: u32 = 3;
+const WATCHDOG_MAX_BACKOFF_SECS: u64 = 60;
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_secs(3);
+const LOG_STREAM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// A partial line longer than this is flushed as-is rather than buffered forever.
+const LOG_STREAM_MAX_LINE_BYTES: usize = 64 * 1024;
+/// Keep the online/offline badge responsive even when the edge box is unreachable.
+const EDGE_PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+/// How far past a busy preferred port `start_agents` looks for a free one.
+const PORT_FALLBACK_RANGE: u16 = 20;
+const AGENT_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const AGENT_LOG_KEEP: usize = 3;
+/// Oldest pos-agent protocol (`pos-agent --version`) this desktop build can drive.
+const MIN_SIDECAR_PROTOCOL: u32 = 1;
+/// First pos-agent protocol with the `--db-stats` / `--vacuum-db` maintenance flags.
+const DB_MAINTENANCE_SIDECAR_PROTOCOL: u32 = 3;
+/// How long a stopping agent gets to flush SQLite writes before it is killed.
+const AGENT_SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+/// Backend device tokens are `token_urlsafe(32)` (43 chars); anything much shorter is a paste error.
+const MIN_DEVICE_TOKEN_LEN: usize = 32;
 
 #[derive(Clone, Debug)]
 struct AgentRuntime {
@@ -18,22 +45,83 @@ struct AgentRuntime {
   log_path: PathBuf,
 }
 
+/// Watchdog bookkeeping per agent, so a crash-looping agent backs off instead of thrashing.
+#[derive(Default)]
+struct RestartBackoff {
+  started_at: Option<Instant>,
+  unhealthy_polls: u32,
+  restarts: u32,
+  not_before: Option<Instant>,
+}
+
+impl RestartBackoff {
+  fn on_spawned(&mut self) {
+    self.started_at = Some(Instant::now());
+    self.unhealthy_polls = 0;
+  }
+
+  fn past_startup_grace(&self) -> bool {
+    self
+      .started_at
+      .map(|t| t.elapsed() >= WATCHDOG_STARTUP_GRACE)
+      .unwrap_or(true)
+  }
+
+  /// Exponential delay (2s, 4s, 8s ... capped) before the next restart is allowed.
+  fn schedule_next(&mut self) {
+    self.restarts = self.restarts.saturating_add(1);
+    let secs = (1u64 << self.restarts.min(6)).min(WATCHDOG_MAX_BACKOFF_SECS);
+    self.not_before = Some(Instant::now() + Duration::from_secs(secs));
+  }
+
+  fn ready(&self) -> bool {
+    self.not_before.map(|t| Instant::now() >= t).unwrap_or(true)
+  }
+}
+
 #[derive(Default)]
 struct AgentsState {
   official: Option<Child>,
   unofficial: Option<Child>,
   official_spec: Option<AgentRuntime>,
   unofficial_spec: Option<AgentRuntime>,
+  official_backoff: RestartBackoff,
+  unofficial_backoff: RestartBackoff,
   watchdog_started: bool,
+  /// Stop flag for the `agent://health` polling loop; `None` when it isn't running.
+  health_monitor_stop: Option<Arc<AtomicBool>>,
+  /// Stop flags for `agent://log` tail loops, keyed by "official"/"unofficial".
+  log_streams: HashMap<&'static str, Arc<AtomicBool>>,
+}
+
+impl AgentsState {
+  fn slot_mut(&mut self, which: &str) -> (&mut Option<Child>, &mut Option<AgentRuntime>, &mut RestartBackoff) {
+    if which == "official" {
+      (&mut self.official, &mut self.official_spec, &mut self.official_backoff)
+    } else {
+      (&mut self.unofficial, &mut self.unofficial_spec, &mut self.unofficial_backoff)
+    }
+  }
+
+  /// Signal the tail thread for `which` (or every stream when `None`) to exit.
+  fn stop_log_streams(&mut self, which: Option<&str>) {
+    self.log_streams.retain(|key, stop| {
+      let matched = which.is_none_or(|w| w == *key);
+      if matched {
+        stop.store(true, Ordering::Relaxed);
+      }
+      !matched
+    });
+  }
 }
 
 impl Drop for AgentsState {
   fn drop(&mut self) {
     if let Some(mut c) = self.official.take() {
-      let _ = c.kill();
+      stop_agent_child(&mut c, self.official_spec.as_ref().map(|s| s.port));
     }
     if let Some(mut c) = self.unofficial.take() {
-      let _ = c.kill();
+      stop_agent_child(&mut c, self.unofficial_spec.as_ref().map(|s| s.port));
     }
   }
 }
@@ -45,6 +133,18 @@ fn app_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     .map_err(|e| format!("failed to resolve app data dir: {e}"))
 }
 
+fn parse_which(which: &str) -> Result<&'static str, String> {
+  match which.trim() {
+    "official" => Ok("official"),
+    "unofficial" => Ok("unofficial"),
+    other => Err(format!("unknown agent: {other} (expected official or unofficial)")),
+  }
+}
+
+fn profile_dir(app: &tauri::AppHandle, which: &str) -> Result<PathBuf, String> {
+  Ok(app_data_dir(app)?.join(parse_which(which)?))
+}
+
 fn ensure_parent_dir(path: &Path) -> std::io::Result<()> {
   if let Some(p) = path.parent() {
     fs::create_dir_all(p)?;
@@ -64,6 +164,10 @@ fn is_port_available(port: u16) -> bool {
 }
 
 fn http_status_for_local_path(port: u16, path: &str, origin: Option<&str>) -> Option<u16> {
+  http_status_for_local_request(port, "GET", path, origin)
+}
+
+fn http_status_for_local_request(port: u16, method: &str, path: &str, origin: Option<&str>) -> Option<u16> {
   let addr: SocketAddr = match format!("127.0.0.1:{port}").parse() {
     Ok(v) => v, Err(_) => return None,
   };
@@ -73,9 +177,12 @@ fn http_status_for_local_path(port: u16, path: &str, origin: Option<&str>) -> Op
   let _ = stream.set_read_timeout(Some(Duration::from_millis(350)));
   let _ = stream.set_write_timeout(Some(Duration::from_millis(350)));
   let mut req = format!(
-    "GET {} HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\nConnection: close\r\n",
+    "{method} {} HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\nConnection: close\r\n",
     if path.trim().is_empty() { "/" } else { path.trim() }
   );
+  if method != "GET" {
+    req.push_str("Content-Length: 0\r\n");
+  }
   if let Some(o) = origin {
     if !o.trim().is_empty() {
       req.push_str(&format!("Origin: {}\r\n", o.trim()));
@@ -106,6 +213,30 @@ fn is_agent_health_ok(port: u16) -> bool {
   matches!(http_status_for_local_path(port, "/api/health", None), Some(200))
 }
 
+/// Ask the agent to exit via loopback `/api/shutdown` so it can finish in-flight SQLite
+/// writes; fall back to `kill()` if it refuses (older sidecar) or outlives the grace period.
+/// Blocks the caller for up to `AGENT_SHUTDOWN_GRACE` per agent, so commands built on it
+/// (`stop_agents`, `restart_agent`, `vacuum_agent_db`, `reset_profile`) can take seconds.
+/// Never call it while holding the state lock.
+fn stop_agent_child(child: &mut Child, port: Option<u16>) {
+  if matches!(child.try_wait(), Ok(Some(_))) {
+    return;
+  }
+  if let Some(port) = port {
+    if matches!(http_status_for_local_request(port, "POST", "/api/shutdown", None), Some(200)) {
+      let deadline = Instant::now() + AGENT_SHUTDOWN_GRACE;
+      while Instant::now() < deadline {
+        if matches!(child.try_wait(), Ok(Some(_))) {
+          return;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+      }
+    }
+  }
+  let _ = child.kill();
+  let _ = child.wait();
+}
+
 fn is_agent_tauri_compatible(port: u16) -> bool {
   matches!(
     http_status_for_local_path(port, "/api/health", Some("tauri://localhost")),
@@ -133,6 +264,154 @@ fn ensure_config_exists(path: &Path) -> std::io::Result<()> {
   Ok(())
 }
 
+fn looks_like_uuid(v: &str) -> bool {
+  v.len() == 36
+    && v.char_indices().all(|(i, c)| match i {
+      8 | 13 | 18 | 23 => c == '-',
+      _ => c.is_ascii_hexdigit(),
+    })
+}
+
+/// Catch paste errors in device credentials before they reach the agent.
+/// Blank device fields are fine: a fresh agent is configured later via Express Setup.
+fn validate_agent_config(cfg: &serde_json::Value) -> Result<(), String> {
+  validate_agent_config_keys(cfg, &|_| true)
+}
+
+/// Like `validate_agent_config`, but only checks the fields `touched` selects, so a
+/// patch isn't rejected for problems in unrelated, pre-existing fields.
+fn validate_agent_config_keys(cfg: &serde_json::Value, touched: &dyn Fn(&str) -> bool) -> Result<(), String> {
+  let field = |k: &str| cfg.get(k).and_then(|v| v.as_str()).unwrap_or("").trim().to_string();
+  let device_id = field("device_id");
+  let device_token = field("device_token");
+
+  // The agent uses the first of cloud/api/edge base URLs that is set.
+  const BASE_URL_KEYS: [&str; 3] = ["cloud_api_base_url", "api_base_url", "edge_api_base_url"];
+  if BASE_URL_KEYS.iter().any(|k| touched(k)) {
+    let mut has_base_url = false;
+    for key in BASE_URL_KEYS {
+      let url = field(key);
+      if url.is_empty() {
+        continue;
+      }
+      if touched(key) && !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("{key} must start with http:// or https:// (got {url})"));
+      }
+      has_base_url = true;
+    }
+    if !has_base_url {
+      return Err("api_base_url is required".to_string());
+    }
+  }
+  if (touched("device_id") || touched("device_token")) && device_id.is_empty() != device_token.is_empty() {
+    return Err("device_id and device_token must be set together".to_string());
+  }
+  if touched("device_id") && !device_id.is_empty() && !looks_like_uuid(&device_id) {
+    return Err(format!("device_id does not look like a device id (expected a UUID, got {device_id})"));
+  }
+  if touched("device_token") && !device_token.is_empty() {
+    if device_token.len() < MIN_DEVICE_TOKEN_LEN {
+      return Err(format!(
+        "device_token is too short ({} chars, expected at least {MIN_DEVICE_TOKEN_LEN})",
+        device_token.len()
+      ));
+    }
+    if device_token.chars().any(|c| c.is_whitespace()) {
+      return Err("device_token must not contain spaces or line breaks".to_string());
+    }
+  }
+  Ok(())
+}
+
+/// The config as the agent will see it: DEFAULT_CONFIG fills missing keys and the
+/// POS_* environment overrides win (mirrors load_config in agent.py).
+fn effective_agent_config(raw: &serde_json::Value) -> serde_json::Value {
+  let mut cfg = raw.clone();
+  let Some(obj) = cfg.as_object_mut() else {
+    return cfg;
+  };
+  obj
+    .entry("api_base_url")
+    .or_insert_with(|| serde_json::Value::String("http://localhost:8001".to_string()));
+  let env = |k: &str| std::env::var(k).ok().filter(|v| !v.is_empty());
+  let cloud_missing = |obj: &serde_json::Map<String, serde_json::Value>| {
+    obj.get("cloud_api_base_url").and_then(|v| v.as_str()).unwrap_or("").trim().is_empty()
+  };
+  if let Some(v) = env("POS_API_BASE_URL") {
+    obj.insert("api_base_url".to_string(), v.clone().into());
+    if cloud_missing(obj) {
+      obj.insert("cloud_api_base_url".to_string(), v.into());
+    }
+  }
+  if let Some(v) = env("POS_EDGE_API_BASE_URL") {
+    obj.insert("edge_api_base_url".to_string(), v.clone().into());
+    if cloud_missing(obj) {
+      obj.insert("cloud_api_base_url".to_string(), v.into());
+    }
+  }
+  for (var, key) in [
+    ("POS_CLOUD_API_BASE_URL", "cloud_api_base_url"),
+    ("POS_DEVICE_ID", "device_id"),
+    ("POS_DEVICE_TOKEN", "device_token"),
+  ] {
+    if let Some(v) = env(var) {
+      obj.insert(key.to_string(), v.into());
+    }
+  }
+  cfg
+}
+
+/// Check the effective config before spawning. Problems are only logged: the agent
+/// can still start and be fixed from Express Setup.
+fn warn_on_agent_config(app: &tauri::AppHandle, which: &str, path: &Path) {
+  let checked = read_agent_config(path).and_then(|cfg| validate_agent_config(&effective_agent_config(&cfg)));
+  if let Err(e) = checked {
+    let _ = append_desktop_log(app, "warn", &format!("{} agent config: {e}", agent_label(which)), None);
+  }
+}
+
+fn read_agent_config(path: &Path) -> Result<serde_json::Value, String> {
+  let raw = fs::read_to_string(path).map_err(|e| e.to_string())?;
+  serde_json::from_str(&raw).map_err(|e| format!("invalid config.json ({}): {e}", path.display()))
+}
+
+fn validate_agent_config_file(path: &Path) -> Result<(), String> {
+  validate_agent_config(&read_agent_config(path)?)
+}
+
+fn config_backup_path(path: &Path) -> PathBuf {
+  path.with_extension("json.bak")
+}
+
+/// Merge top-level keys from `patch` into config.json, keeping everything else.
+/// Writes via a temp file + rename, like the agent's own save_config.
+fn patch_config_file(path: &Path, patch: &serde_json::Map<String, serde_json::Value>) -> Result<(), String> {
+  ensure_config_exists(path).map_err(|e| e.to_string())?;
+  let raw = fs::read_to_string(path).map_err(|e| e.to_string())?;
+  let mut cfg: serde_json::Value = serde_json::from_str(&raw)
+    .map_err(|e| format!("invalid config.json ({}): {e}", path.display()))?;
+  let obj = cfg
+    .as_object_mut()
+    .ok_or_else(|| format!("invalid config.json ({}): not an object", path.display()))?;
+  for (k, v) in patch {
+    obj.insert(k.clone(), v.clone());
+  }
+  validate_agent_config_keys(&cfg, &|k| patch.contains_key(k))?;
+  let json_str = serde_json::to_string_pretty(&cfg).map_err(|e| e.to_string())?;
+  // Keep the previous config so restore_config can undo a bad patch.
+  fs::copy(path, config_backup_path(path)).map_err(|e| format!("config backup failed: {e}"))?;
+  write_file_atomic(path, json_str.as_bytes())
+}
+
+/// Replace `path` atomically via a sibling temp file + rename.
+fn write_file_atomic(path: &Path, bytes: &[u8]) -> Result<(), String> {
+  let mut tmp = path.as_os_str().to_owned();
+  tmp.push(".tmp");
+  let tmp = PathBuf::from(tmp);
+  fs::write(&tmp, bytes).map_err(|e| e.to_string())?;
+  fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
 fn find_sidecar_exe(app: &tauri::AppHandle) -> Option<PathBuf> {
   let res = app.path().resource_dir().ok()?;
   let candidates = if cfg!(target_os = "windows") {
@@ -149,6 +428,66 @@ fn find_sidecar_exe(app: &tauri::AppHandle) -> Option<PathBuf> {
   candidates.into_iter().find(|c| c.exists())
 }
 
+/// Keep `preferred` if it is free or already served by a compatible agent; otherwise
+/// scan the next few ports and announce the replacement via `agent://port`.
+fn pick_agent_port(app: &tauri::AppHandle, which: &str, preferred: u16, taken: Option<u16>) -> Result<u16, String> {
+  let usable = Some(preferred) != taken
+    && (is_port_available(preferred)
+      || (is_agent_health_ok(preferred) && is_agent_tauri_compatible(preferred)));
+  if usable {
+    return Ok(preferred);
+  }
+  let port = (1..=PORT_FALLBACK_RANGE)
+    .filter_map(|i| preferred.checked_add(i))
+    .find(|p| Some(*p) != taken && is_port_available(*p))
+    .ok_or_else(|| {
+      format!(
+        "port {preferred} is already in use and no free port was found up to {}",
+        preferred.saturating_add(PORT_FALLBACK_RANGE)
+      )
+    })?;
+  let _ = append_desktop_log(
+    app,
+    "warn",
+    &format!("{} agent port {preferred} is busy; using {port} instead", agent_label(which)),
+    None,
+  );
+  let _ = app.emit(
+    "agent://port",
+    serde_json::json!({
+      "which": which,
+      "requested": preferred,
+      "port": port,
+    }),
+  );
+  Ok(port)
+}
+
+fn rotated_log_path(path: &Path, n: usize) -> PathBuf {
+  let mut name = path.as_os_str().to_owned();
+  name.push(format!(".{n}"));
+  PathBuf::from(name)
+}
+
+/// Best-effort size-based rotation: `x.log` -> `x.log.1` -> ... -> `x.log.N` (oldest dropped).
+fn rotate_log_if_needed(path: &Path) {
+  let size = match fs::metadata(path) {
+    Ok(m) => m.len(),
+    Err(_) => return,
+  };
+  if size < AGENT_LOG_MAX_BYTES {
+    return;
+  }
+  let _ = fs::remove_file(rotated_log_path(path, AGENT_LOG_KEEP));
+  for n in (1..AGENT_LOG_KEEP).rev() {
+    let from = rotated_log_path(path, n);
+    if from.exists() {
+      let _ = fs::rename(&from, rotated_log_path(path, n + 1));
+    }
+  }
+  let _ = fs::rename(path, rotated_log_path(path, 1));
+}
+
 fn spawn_agent(
   app: &tauri::AppHandle,
   port: u16,
@@ -164,6 +503,7 @@ fn spawn_agent(
   })?;
 
   ensure_parent_dir(log_path)?;
+  rotate_log_if_needed(log_path);
   let log = OpenOptions::new()
     .create(true)
     .append(true)
@@ -197,6 +537,14 @@ fn spawn_agent_from_spec(app: &tauri::AppHandle, spec: &AgentRuntime) -> std::io
   spawn_agent(app, spec.port, &spec.config_path, &spec.db_path, &spec.log_path)
 }
 
+fn agent_label(which: &str) -> &'static str {
+  if which == "official" {
+    "primary"
+  } else {
+    "secondary"
+  }
+}
+
 fn ensure_watchdog_running(app: &tauri::AppHandle) {
   let should_start = {
     let state: tauri::State<'_, Mutex<AgentsState>> = app.state();
@@ -214,100 +562,205 @@ fn ensure_watchdog_running(app: &tauri::AppHandle) {
 
   let app_handle = app.clone();
   std::thread::spawn(move || loop {
-    std::thread::sleep(Duration::from_secs(2));
+    std::thread::sleep(WATCHDOG_INTERVAL);
+    watchdog_tick(&app_handle, "official");
+    watchdog_tick(&app_handle, "unofficial");
+  });
+}
 
-    let mut restart_official: Option<AgentRuntime> = None;
-    let mut restart_unofficial: Option<AgentRuntime> = None;
-    {
-      let state: tauri::State<'_, Mutex<AgentsState>> = app_handle.state();
-      let mut st = lock_or_recover(&state);
+fn watchdog_tick(app: &tauri::AppHandle, which: &'static str) {
+  let state: tauri::State<'_, Mutex<AgentsState>> = app.state();
+  let label = agent_label(which);
 
-      if let Some(child) = st.official.as_mut() {
-        if matches!(child.try_wait(), Ok(Some(_))) {
-          st.official = None;
-        }
-      }
-      if let Some(child) = st.unofficial.as_mut() {
-        if matches!(child.try_wait(), Ok(Some(_))) {
-          st.unofficial = None;
-        }
+  // Reap an exited child and decide whether a live one is due for a health probe.
+  let probe_port = {
+    let mut st = lock_or_recover(&state);
+    let (child, spec, backoff) = st.slot_mut(which);
+    if let Some(c) = child.as_mut() {
+      if matches!(c.try_wait(), Ok(Some(_))) {
+        *child = None;
       }
+    }
+    match (child.as_ref(), spec.as_ref()) {
+      (Some(_), Some(s)) if backoff.past_startup_grace() => Some(s.port),
+      _ => None,
+    }
+  };
 
-      if st.official.is_none() {
-        if let Some(spec) = st.official_spec.clone() {
-          if is_port_available(spec.port) {
-            restart_official = Some(spec);
-          }
-        }
-      }
-      if st.unofficial.is_none() {
-        if let Some(spec) = st.unofficial_spec.clone() {
-          if is_port_available(spec.port) {
-            restart_unofficial = Some(spec);
-          }
+  // Probe without holding the lock; a hung agent can take the full connect timeout.
+  if let Some(port) = probe_port {
+    let healthy = is_agent_health_ok(port);
+    let mut st = lock_or_recover(&state);
+    let (child, _spec, backoff) = st.slot_mut(which);
+    if healthy {
+      backoff.unhealthy_polls = 0;
+      backoff.restarts = 0;
+    } else {
+      backoff.unhealthy_polls += 1;
+      if backoff.unhealthy_polls >= WATCHDOG_UNHEALTHY_POLLS {
+        let hung = child.take();
+        // Stop outside the lock: the shutdown grace period would stall every other command.
+        drop(st);
+        if let Some(mut c) = hung {
+          stop_agent_child(&mut c, Some(port));
         }
+        let _ = append_desktop_log(
+          app,
+          "warn",
+          &format!("watchdog killed unresponsive {label} agent on port {port}"),
+          None,
+        );
       }
     }
+  }
 
-    if let Some(spec) = restart_official {
-      match spawn_agent_from_spec(&app_handle, &spec) {
-        Ok(child) => {
-          let state: tauri::State<'_, Mutex<AgentsState>> = app_handle.state();
-          let mut st = lock_or_recover(&state);
-          if st.official.is_none() {
-            st.official = Some(child);
-            let _ = append_desktop_log(
-              &app_handle,
-              "warn",
-              &format!("watchdog restarted primary agent on port {}", spec.port),
-              None,
-            );
-          }
-        }
-        Err(e) => {
-          let _ = append_desktop_log(
-            &app_handle,
-            "error",
-            &format!("watchdog failed to restart primary agent: {}", e),
-            None,
-          );
-        }
-      }
+  let spec = {
+    let mut st = lock_or_recover(&state);
+    let (child, spec, backoff) = st.slot_mut(which);
+    match spec {
+      Some(s) if child.is_none() && backoff.ready() && is_port_available(s.port) => s.clone(),
+      _ => return,
     }
+  };
 
-    if let Some(spec) = restart_unofficial {
-      match spawn_agent_from_spec(&app_handle, &spec) {
-        Ok(child) => {
-          let state: tauri::State<'_, Mutex<AgentsState>> = app_handle.state();
-          let mut st = lock_or_recover(&state);
-          if st.unofficial.is_none() {
-            st.unofficial = Some(child);
-            let _ = append_desktop_log(
-              &app_handle,
-              "warn",
-              &format!("watchdog restarted secondary agent on port {}", spec.port),
-              None,
-            );
-          }
-        }
-        Err(e) => {
-          let _ = append_desktop_log(
-            &app_handle,
-            "error",
-            &format!("watchdog failed to restart secondary agent: {}", e),
-            None,
-          );
+  match spawn_agent_from_spec(app, &spec) {
+    Ok(mut new_child) => {
+      let restarts = {
+        let mut st = lock_or_recover(&state);
+        let (child, _spec, backoff) = st.slot_mut(which);
+        if child.is_some() {
+          // start_agents won the race; don't leak a second process on the same port.
+          drop(st);
+          stop_agent_child(&mut new_child, None);
+          return;
         }
+        *child = Some(new_child);
+        backoff.on_spawned();
+        backoff.schedule_next();
+        backoff.restarts
+      };
+      let _ = append_desktop_log(
+        app,
+        "warn",
+        &format!("watchdog restarted {label} agent on port {} (restart #{restarts})", spec.port),
+        None,
+      );
+      let _ = app.emit(
+        "agent://restarted",
+        serde_json::json!({
+          "which": which,
+          "port": spec.port,
+          "restarts": restarts,
+        }),
+      );
+    }
+    Err(e) => {
+      {
+        let mut st = lock_or_recover(&state);
+        st.slot_mut(which).2.schedule_next();
       }
+      let _ = append_desktop_log(
+        app,
+        "error",
+        &format!("watchdog failed to restart {label} agent: {}", e),
+        None,
+      );
+    }
+  }
+}
+
+fn start_health_monitor(app: &tauri::AppHandle) {
+  let stop = {
+    let state: tauri::State<'_, Mutex<AgentsState>> = app.state();
+    let mut st = lock_or_recover(&state);
+    if st.health_monitor_stop.is_some() {
+      return;
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    st.health_monitor_stop = Some(stop.clone());
+    stop
+  };
+
+  let app_handle = app.clone();
+  std::thread::spawn(move || loop {
+    std::thread::sleep(HEALTH_POLL_INTERVAL);
+    if stop.load(Ordering::Relaxed) {
+      break;
+    }
+    let (official_port, unofficial_port) = {
+      let state: tauri::State<'_, Mutex<AgentsState>> = app_handle.state();
+      let st = lock_or_recover(&state);
+      (
+        st.official_spec.as_ref().map(|s| s.port),
+        st.unofficial_spec.as_ref().map(|s| s.port),
+      )
+    };
+    let official = official_port.map(is_agent_health_ok).unwrap_or(false);
+    let unofficial = unofficial_port.map(is_agent_health_ok).unwrap_or(false);
+    // Re-check after probing so a stop during the probe doesn't emit a stale all-red event.
+    if stop.load(Ordering::Relaxed) {
+      break;
     }
+    let _ = app_handle.emit(
+      "agent://health",
+      serde_json::json!({
+        "official": official,
+        "unofficial": unofficial,
+      }),
+    );
   });
 }
 
+/// Protocol version reported by the bundled sidecar. Sidecars that predate `--version`
+/// exit non-zero on the unknown flag and are reported as protocol 0.
+fn sidecar_protocol(app: &tauri::AppHandle) -> Result<u32, String> {
+  let sidecar = find_sidecar_exe(app)
+    .ok_or_else(|| "pos-agent sidecar not found (bundle it for production builds)".to_string())?;
+  let mut cmd = Command::new(sidecar);
+  cmd.arg("--version");
+
+  #[cfg(target_os = "windows")]
+  {
+    use std::os::windows::process::CommandExt;
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+  }
+
+  let out = cmd.output().map_err(|e| e.to_string())?;
+  if !out.status.success() {
+    return Ok(0);
+  }
+  let text = String::from_utf8_lossy(&out.stdout);
+  text
+    .trim()
+    .parse::<u32>()
+    .map_err(|_| format!("unexpected pos-agent --version output: {}", text.trim()))
+}
+
+fn ensure_sidecar_protocol(app: &tauri::AppHandle, min: u32) -> Result<(), String> {
+  let protocol = sidecar_protocol(app)?;
+  if protocol < min {
+    return Err(format!(
+      "pos-agent sidecar protocol {protocol} is older than POS Desktop {} requires ({min}). Reinstall POS Desktop or rebuild the sidecar.",
+      env!("CARGO_PKG_VERSION")
+    ));
+  }
+  Ok(())
+}
+
+fn ensure_sidecar_compatible(app: &tauri::AppHandle) -> Result<(), String> {
+  ensure_sidecar_protocol(app, MIN_SIDECAR_PROTOCOL)
+}
+
 fn init_db_with_sidecar(app: &tauri::AppHandle, config_path: &Path, db_path: &Path) -> Result<(), String> {
+  run_sidecar_db_task(app, "--init-db", config_path, db_path).map(|_| ())
+}
+
+/// Run a one-shot sidecar task (`--init-db`, `--vacuum-db`, `--db-stats`) and return its stdout.
+fn run_sidecar_db_task(app: &tauri::AppHandle, flag: &str, config_path: &Path, db_path: &Path) -> Result<String, String> {
   let sidecar = find_sidecar_exe(app)
     .ok_or_else(|| "pos-agent sidecar not found (bundle it for production builds)".to_string())?;
   let mut cmd = Command::new(sidecar);
-  cmd.arg("--init-db")
+  cmd.arg(flag)
     .arg("--config")
     .arg(config_path.to_string_lossy().to_string())
     .arg("--db")
@@ -322,11 +775,11 @@ fn init_db_with_sidecar(app: &tauri::AppHandle, config_path: &Path, db_path: &Pa
   let out = cmd.output().map_err(|e| e.to_string())?;
 
   if out.status.success() {
-    return Ok(());
+    return Ok(String::from_utf8_lossy(&out.stdout).to_string());
   }
 
   let mut msg = String::new();
-  msg.push_str("init-db failed.\n");
+  msg.push_str(&format!("{} failed.\n", flag.trim_start_matches('-')));
   if !out.stdout.is_empty() {
     msg.push_str(&String::from_utf8_lossy(&out.stdout));
   }
@@ -337,6 +790,33 @@ fn init_db_with_sidecar(app: &tauri::AppHandle, config_path: &Path, db_path: &Pa
   Err(msg.trim().to_string())
 }
 
+/// Config/DB paths for an agent: the running spec if any, else the default profile layout.
+fn agent_db_paths(
+  app: &tauri::AppHandle,
+  state: &Mutex<AgentsState>,
+  which: &str,
+) -> Result<(PathBuf, PathBuf), String> {
+  let mut st = lock_or_recover(state);
+  if let Some(spec) = st.slot_mut(which).1.as_ref() {
+    return Ok((spec.config_path.clone(), spec.db_path.clone()));
+  }
+  drop(st);
+  let dir = profile_dir(app, which)?;
+  Ok((dir.join("config.json"), dir.join("pos.sqlite")))
+}
+
+/// Size of the SQLite file plus its WAL/SHM sidecars.
+fn sqlite_file_bytes(db_path: &Path) -> u64 {
+  ["", "-wal", "-shm"]
+    .iter()
+    .map(|suffix| {
+      let mut name = db_path.as_os_str().to_owned();
+      name.push(suffix);
+      fs::metadata(PathBuf::from(name)).map(|m| m.len()).unwrap_or(0)
+    })
+    .sum()
+}
+
 // ---------------------------------------------------------------------------
 // Tauri commands
 // ---------------------------------------------------------------------------
@@ -347,11 +827,21 @@ fn start_agents(
   state: tauri::State<'_, Mutex<AgentsState>>,
   port_official: u16,
   port_unofficial: u16,
-) -> Result<(), String> {
+  strict_ports: Option<bool>,
+) -> Result<serde_json::Value, String> {
   if port_official == port_unofficial {
     return Err("primary and secondary ports must be different".to_string());
   }
 
+  // Unless the operator pinned the ports, move off ports held by unrelated processes.
+  let (port_official, port_unofficial) = if strict_ports.unwrap_or(false) {
+    (port_official, port_unofficial)
+  } else {
+    let off = pick_agent_port(&app, "official", port_official, Some(port_unofficial))?;
+    let un = pick_agent_port(&app, "unofficial", port_unofficial, Some(off))?;
+    (off, un)
+  };
+
   let data = app_data_dir(&app)?;
   let official_cfg = data.join("official").join("config.json");
   let unofficial_cfg = data.join("unofficial").join("config.json");
@@ -397,12 +887,18 @@ fn start_agents(
   ensure_config_exists(&official_cfg).map_err(|e| e.to_string())?;
   ensure_config_exists(&unofficial_cfg).map_err(|e| e.to_string())?;
 
-  // Preflight DB init only for agents we actually need to spawn.
+  if !official_busy || !unofficial_busy {
+    ensure_sidecar_compatible(&app)?;
+  }
+
+  // Preflight config + DB init only for agents we actually need to spawn.
   if !official_busy {
+    warn_on_agent_config(&app, "official", &official_cfg);
     init_db_with_sidecar(&app, &official_cfg, &official_db)
       .map_err(|e| format!("Primary agent DB init failed: {e}"))?;
   }
   if !unofficial_busy {
+    warn_on_agent_config(&app, "unofficial", &unofficial_cfg);
     init_db_with_sidecar(&app, &unofficial_cfg, &unofficial_db)
       .map_err(|e| format!("Secondary agent DB init failed: {e}"))?;
   }
@@ -410,15 +906,19 @@ fn start_agents(
   let mut st = lock_or_recover(&state);
   st.official_spec = Some(official_spec);
   st.unofficial_spec = Some(unofficial_spec);
+  st.official_backoff = RestartBackoff::default();
+  st.unofficial_backoff = RestartBackoff::default();
   if st.official.is_none() && !official_busy {
     let child = spawn_agent(&app, port_official, &official_cfg, &official_db, &official_log)
       .map_err(|e| e.to_string())?;
     st.official = Some(child);
+    st.official_backoff.on_spawned();
   }
   if st.unofficial.is_none() && !unofficial_busy {
     let child = spawn_agent(&app, port_unofficial, &unofficial_cfg, &unofficial_db, &unofficial_log)
       .map_err(|e| e.to_string())?;
     st.unofficial = Some(child);
+    st.unofficial_backoff.on_spawned();
   }
 
   // If a child exits immediately, return log tail to make failures actionable.
@@ -438,23 +938,333 @@ fn start_agents(
 
   drop(st);
   ensure_watchdog_running(&app);
-  Ok(())
+  start_health_monitor(&app);
+  // Ports may differ from the requested ones after fallback; the UI must use these.
+  Ok(serde_json::json!({
+    "port_official": port_official,
+    "port_unofficial": port_unofficial,
+  }))
 }
 
+/// Blocks until both agents have exited: up to 2x `AGENT_SHUTDOWN_GRACE` if they drain slowly.
 #[tauri::command]
 fn stop_agents(state: tauri::State<'_, Mutex<AgentsState>>) -> Result<(), String> {
-  let mut st = lock_or_recover(&state);
-  if let Some(mut c) = st.official.take() {
-    let _ = c.kill();
+  let (official, unofficial) = {
+    let mut st = lock_or_recover(&state);
+    let official = st.official.take().map(|c| (c, st.official_spec.as_ref().map(|s| s.port)));
+    let unofficial = st.unofficial.take().map(|c| (c, st.unofficial_spec.as_ref().map(|s| s.port)));
+    st.official_spec = None;
+    st.unofficial_spec = None;
+    if let Some(stop) = st.health_monitor_stop.take() {
+      stop.store(true, Ordering::Relaxed);
+    }
+    st.stop_log_streams(None);
+    (official, unofficial)
+  };
+  // Specs are cleared first so the watchdog won't respawn while we wait for a clean exit.
+  if let Some((mut c, port)) = official {
+    stop_agent_child(&mut c, port);
   }
-  if let Some(mut c) = st.unofficial.take() {
-    let _ = c.kill();
+  if let Some((mut c, port)) = unofficial {
+    stop_agent_child(&mut c, port);
   }
-  st.official_spec = None;
-  st.unofficial_spec = None;
   Ok(())
 }
 
+/// Restart one agent with its last-known spec, leaving the other till untouched.
+/// Can block ~8s: the shutdown grace, the 3s port-release wait, then the exit check.
+#[tauri::command]
+fn restart_agent(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, Mutex<AgentsState>>,
+  which: String,
+) -> Result<(), String> {
+  let which = parse_which(&which)?;
+  let label = agent_label(which);
+  // Park the spec while the lock is released so the watchdog doesn't respawn mid-restart.
+  let (old_child, spec) = {
+    let mut st = lock_or_recover(&state);
+    let (child, spec, _) = st.slot_mut(which);
+    let parked = spec
+      .take()
+      .ok_or_else(|| format!("{label} agent is not configured (start agents first)"))?;
+    (child.take(), parked)
+  };
+
+  let spawned = (|| {
+    if let Some(mut c) = old_child {
+      stop_agent_child(&mut c, Some(spec.port));
+    }
+    // Give the OS a moment to release the listening socket.
+    let deadline = Instant::now() + Duration::from_secs(3);
+    while !is_port_available(spec.port) {
+      if Instant::now() >= deadline {
+        return Err(format!(
+          "port {} is still in use; {label} agent may be running outside POS Desktop",
+          spec.port
+        ));
+      }
+      std::thread::sleep(Duration::from_millis(100));
+    }
+
+    let mut new_child = spawn_agent_from_spec(&app, &spec).map_err(|e| e.to_string())?;
+    std::thread::sleep(Duration::from_millis(250));
+    if let Ok(Some(status)) = new_child.try_wait() {
+      let tail = tail_file(&spec.log_path, 120_000, 80);
+      return Err(format!("{label} agent exited ({status}).\n{tail}").trim().to_string());
+    }
+    Ok(new_child)
+  })();
+
+  let mut st = lock_or_recover(&state);
+  let (child, slot_spec, backoff) = st.slot_mut(which);
+  if slot_spec.is_some() || child.is_some() {
+    // stop_agents/start_agents ran meanwhile; their state wins.
+    drop(st);
+    if let Ok(mut c) = spawned {
+      stop_agent_child(&mut c, None);
+    }
+    return Err(format!("{label} agent was reconfigured during restart"));
+  }
+  *slot_spec = Some(spec.clone());
+  *backoff = RestartBackoff::default();
+  // On failure the spec is still restored, so the watchdog keeps retrying.
+  *child = Some(spawned?);
+  backoff.on_spawned();
+  drop(st);
+
+  let _ = append_desktop_log(
+    &app,
+    "info",
+    &format!("restarted {label} agent on port {}", spec.port),
+    None,
+  );
+  Ok(())
+}
+
+/// Per-agent runtime status: `{running, pid, port, healthy, external, uptime_secs, config_path}`.
+/// `external` means the port is served by an agent POS Desktop did not spawn.
+#[tauri::command]
+fn agent_status(state: tauri::State<'_, Mutex<AgentsState>>) -> Result<serde_json::Value, String> {
+  let mut out = serde_json::Map::new();
+  for which in ["official", "unofficial"] {
+    let (spec, pid, uptime_secs) = {
+      let mut st = lock_or_recover(&state);
+      let (child, spec, backoff) = st.slot_mut(which);
+      if let Some(c) = child.as_mut() {
+        if matches!(c.try_wait(), Ok(Some(_))) {
+          *child = None;
+        }
+      }
+      let pid = child.as_ref().map(|c| c.id());
+      let uptime = pid.and(backoff.started_at).map(|t| t.elapsed().as_secs());
+      (spec.clone(), pid, uptime)
+    };
+
+    let status = match spec {
+      Some(spec) => {
+        let healthy = is_agent_health_ok(spec.port);
+        let external = pid.is_none() && !is_port_available(spec.port) && healthy;
+        serde_json::json!({
+          "running": pid.is_some() || external,
+          "pid": pid,
+          "port": spec.port,
+          "healthy": healthy,
+          "external": external,
+          "uptime_secs": uptime_secs,
+          "config_path": spec.config_path.to_string_lossy(),
+        })
+      }
+      None => serde_json::json!({
+        "running": false,
+        "pid": null,
+        "port": null,
+        "healthy": false,
+        "external": false,
+        "uptime_secs": null,
+        "config_path": null,
+      }),
+    };
+    out.insert(which.to_string(), status);
+  }
+  Ok(serde_json::Value::Object(out))
+}
+
+/// Row counts per table plus file size. Read-only, so the agent keeps running (WAL allows it).
+#[tauri::command]
+fn agent_db_stats(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, Mutex<AgentsState>>,
+  which: String,
+) -> Result<serde_json::Value, String> {
+  let which = parse_which(&which)?;
+  let (config_path, db_path) = agent_db_paths(&app, &state, which)?;
+  if !db_path.exists() {
+    return Err(format!("{} agent has no database yet", agent_label(which)));
+  }
+  ensure_sidecar_protocol(&app, DB_MAINTENANCE_SIDECAR_PROTOCOL)?;
+  let out = run_sidecar_db_task(&app, "--db-stats", &config_path, &db_path)?;
+  let mut stats: serde_json::Value = serde_json::from_str(out.trim())
+    .map_err(|e| format!("unexpected db-stats output: {e}"))?;
+  stats["file_bytes"] = serde_json::json!(sqlite_file_bytes(&db_path));
+  stats["db_path"] = serde_json::json!(db_path.to_string_lossy());
+  Ok(stats)
+}
+
+/// VACUUM an agent DB. The agent is stopped for the duration (VACUUM needs exclusive
+/// access) and restarted afterwards. Its spec is parked while the lock is released so
+/// the watchdog doesn't respawn it mid-VACUUM.
+#[tauri::command]
+fn vacuum_agent_db(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, Mutex<AgentsState>>,
+  which: String,
+) -> Result<serde_json::Value, String> {
+  let which = parse_which(&which)?;
+  let label = agent_label(which);
+  let (config_path, db_path) = agent_db_paths(&app, &state, which)?;
+  if !db_path.exists() {
+    return Err(format!("{label} agent has no database yet"));
+  }
+  ensure_sidecar_protocol(&app, DB_MAINTENANCE_SIDECAR_PROTOCOL)?;
+
+  let (child, spec) = {
+    let mut st = lock_or_recover(&state);
+    let (child, spec, _) = st.slot_mut(which);
+    if child.is_none() {
+      if let Some(s) = spec.as_ref() {
+        if !is_port_available(s.port) {
+          return Err(format!(
+            "{label} agent on port {} is running outside POS Desktop; stop it before running maintenance",
+            s.port
+          ));
+        }
+      }
+    }
+    (child.take(), spec.take())
+  };
+  let was_running = match child {
+    Some(mut c) => {
+      stop_agent_child(&mut c, spec.as_ref().map(|s| s.port));
+      true
+    }
+    None => false,
+  };
+
+  let before_bytes = sqlite_file_bytes(&db_path);
+  let result = run_sidecar_db_task(&app, "--vacuum-db", &config_path, &db_path);
+  let after_bytes = sqlite_file_bytes(&db_path);
+
+  let mut restarted = false;
+  let mut restart_error: Option<String> = None;
+  if let Some(spec) = spec {
+    let mut st = lock_or_recover(&state);
+    let (child, slot_spec, backoff) = st.slot_mut(which);
+    // stop_agents/start_agents may have run meanwhile; don't clobber what they set.
+    if slot_spec.is_none() && child.is_none() {
+      // Restore the spec first so the watchdog keeps retrying if this spawn fails.
+      *slot_spec = Some(spec.clone());
+      *backoff = RestartBackoff::default();
+      if was_running {
+        match spawn_agent_from_spec(&app, &spec) {
+          Ok(new_child) => {
+            *child = Some(new_child);
+            backoff.on_spawned();
+            restarted = true;
+          }
+          Err(e) => restart_error = Some(format!("{label} agent failed to restart after maintenance: {e}")),
+        }
+      }
+    }
+  }
+  if let Some(e) = restart_error.as_deref() {
+    let _ = append_desktop_log(&app, "warn", e, None);
+  }
+  result?;
+
+  let _ = append_desktop_log(
+    &app,
+    "info",
+    &format!("vacuumed {label} agent DB: {before_bytes} -> {after_bytes} bytes"),
+    None,
+  );
+  Ok(serde_json::json!({
+    "before_bytes": before_bytes,
+    "after_bytes": after_bytes,
+    "restarted": restarted,
+    "restart_error": restart_error,
+  }))
+}
+
+/// Wipe a profile's local state (config + SQLite) so the terminal can be redeployed.
+/// Stops the agent and forgets its spec; start agents again to reconfigure from scratch.
+#[tauri::command]
+fn reset_profile(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, Mutex<AgentsState>>,
+  which: String,
+  confirm: bool,
+) -> Result<Vec<String>, String> {
+  let which = parse_which(&which)?;
+  let label = agent_label(which);
+  if !confirm {
+    return Err(format!("reset of {label} profile not confirmed"));
+  }
+  let dir = profile_dir(&app, which)?;
+
+  let running = {
+    let mut st = lock_or_recover(&state);
+    let (child, spec, backoff) = st.slot_mut(which);
+    if child.is_none() {
+      if let Some(s) = spec.as_ref() {
+        if !is_port_available(s.port) {
+          return Err(format!(
+            "{label} agent on port {} is running outside POS Desktop; stop it before resetting",
+            s.port
+          ));
+        }
+      }
+    }
+    let port = spec.as_ref().map(|s| s.port);
+    // Clear the spec first so the watchdog doesn't respawn into the wiped profile.
+    *spec = None;
+    *backoff = RestartBackoff::default();
+    let running = child.take().map(|c| (c, port));
+    // Don't keep tailing a log whose profile is about to be wiped.
+    st.stop_log_streams(Some(which));
+    running
+  };
+  if let Some((mut c, port)) = running {
+    stop_agent_child(&mut c, port);
+  }
+
+  let config_path = dir.join("config.json");
+  let db_path = dir.join("pos.sqlite");
+  let mut targets = vec![config_path.clone(), config_backup_path(&config_path)];
+  for suffix in ["", "-wal", "-shm"] {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(suffix);
+    targets.push(PathBuf::from(name));
+  }
+
+  let mut removed = vec![];
+  for path in targets {
+    if !path.exists() {
+      continue;
+    }
+    fs::remove_file(&path).map_err(|e| format!("failed to remove {}: {e}", path.display()))?;
+    removed.push(path.to_string_lossy().to_string());
+  }
+
+  let _ = append_desktop_log(
+    &app,
+    "warn",
+    &format!("reset {label} profile; removed: {}", removed.join(", ")),
+    None,
+  );
+  Ok(removed)
+}
+
 fn tail_file(path: &Path, max_bytes: usize, max_lines: usize) -> String {
   let mut f = match fs::File::open(path) {
     Ok(v) => v,
@@ -519,6 +1329,94 @@ fn tail_agent_logs(app: tauri::AppHandle, max_lines: Option<usize>) -> Result<se
   }))
 }
 
+/// Follow an agent log from its current end, emitting each new line as `agent://log`.
+/// Survives rotation/truncation by reopening from the start when the file shrinks.
+#[tauri::command]
+fn start_log_stream(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, Mutex<AgentsState>>,
+  which: String,
+) -> Result<(), String> {
+  let which = parse_which(&which)?;
+  let log_path = {
+    let mut st = lock_or_recover(&state);
+    match st.slot_mut(which).1.as_ref() {
+      Some(spec) => spec.log_path.clone(),
+      None => app_data_dir(&app)?.join("logs").join(format!("{which}.log")),
+    }
+  };
+  let stop = {
+    let mut st = lock_or_recover(&state);
+    if st.log_streams.contains_key(which) {
+      return Ok(());
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    st.log_streams.insert(which, stop.clone());
+    stop
+  };
+
+  let app_handle = app.clone();
+  std::thread::spawn(move || {
+    let mut pos = fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0);
+    // Raw bytes: a poll can end mid-way through a multi-byte UTF-8 character.
+    let mut pending: Vec<u8> = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+      std::thread::sleep(LOG_STREAM_POLL_INTERVAL);
+      let len = match fs::metadata(&log_path) {
+        Ok(m) => m.len(),
+        Err(_) => continue,
+      };
+      if len < pos {
+        // Rotated or truncated: start over on the new file.
+        pos = 0;
+        pending.clear();
+      }
+      if len == pos {
+        continue;
+      }
+      let mut f = match fs::File::open(&log_path) {
+        Ok(v) => v,
+        Err(_) => continue,
+      };
+      if f.seek(SeekFrom::Start(pos)).is_err() {
+        continue;
+      }
+      let mut buf = Vec::new();
+      if f.read_to_end(&mut buf).is_err() {
+        continue;
+      }
+      pos += buf.len() as u64;
+      pending.extend_from_slice(&buf);
+      let emit_line = |line: &[u8]| {
+        let _ = app_handle.emit(
+          "agent://log",
+          serde_json::json!({
+            "which": which,
+            "line": String::from_utf8_lossy(line).trim_end(),
+          }),
+        );
+      };
+      while let Some(idx) = pending.iter().position(|b| *b == b'\n') {
+        let line: Vec<u8> = pending.drain(..=idx).collect();
+        emit_line(&line);
+      }
+      if pending.len() > LOG_STREAM_MAX_LINE_BYTES {
+        emit_line(&pending);
+        pending.clear();
+      }
+    }
+  });
+  Ok(())
+}
+
+/// Stop one log stream, or all of them when `which` is omitted.
+#[tauri::command]
+fn stop_log_stream(state: tauri::State<'_, Mutex<AgentsState>>, which: Option<String>) -> Result<(), String> {
+  let which = which.as_deref().map(parse_which).transpose()?;
+  lock_or_recover(&state).stop_log_streams(which);
+  Ok(())
+}
+
 #[tauri::command]
 fn frontend_log(
   app: tauri::AppHandle,
@@ -566,6 +1464,220 @@ fn suggest_port_pair(start_official: u16, start_unofficial: u16, max_attempts: O
   Err("No free port pair found near configured ports.".to_string())
 }
 
+#[tauri::command]
+fn patch_config(app: tauri::AppHandle, which: String, patch: serde_json::Value) -> Result<(), String> {
+  let patch = patch
+    .as_object()
+    .ok_or_else(|| "patch must be a JSON object".to_string())?;
+  let path = profile_dir(&app, &which)?.join("config.json");
+  patch_config_file(&path, patch)
+}
+
+/// Swap config.json with the backup taken by the last patch (so a second restore redoes it),
+/// then restart that agent if it is running under POS Desktop.
+/// Reports `{ restored, restarted, restart_error }`.
+#[tauri::command]
+fn restore_config(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, Mutex<AgentsState>>,
+  which: String,
+) -> Result<serde_json::Value, String> {
+  let which = parse_which(&which)?;
+  let path = profile_dir(&app, which)?.join("config.json");
+  let backup = config_backup_path(&path);
+  if !backup.exists() {
+    return Err(format!("no config backup for {} agent", agent_label(which)));
+  }
+  validate_agent_config_file(&backup).map_err(|e| format!("config backup is not usable: {e}"))?;
+  // Swap contents via tmp-file + rename, so config.json is never missing even if a step fails.
+  let restored = fs::read(&backup).map_err(|e| e.to_string())?;
+  let current = fs::read(&path).map_err(|e| e.to_string())?;
+  write_file_atomic(&path, &restored)?;
+  write_file_atomic(&backup, &current)?;
+
+  let _ = append_desktop_log(
+    &app,
+    "warn",
+    &format!("restored {} agent config from backup", agent_label(which)),
+    None,
+  );
+  let configured = {
+    let mut st = lock_or_recover(&state);
+    st.slot_mut(which).1.is_some()
+  };
+  // The config is already restored on disk; a failed restart doesn't undo that,
+  // so report it alongside success instead of as an error.
+  let restart_error = if configured {
+    restart_agent(app, state, which.to_string()).err()
+  } else {
+    None
+  };
+  Ok(serde_json::json!({
+    "restored": true,
+    "restarted": configured && restart_error.is_none(),
+    "restart_error": restart_error,
+  }))
+}
+
+/// Apply a scanned/pasted onboarding device pack to the official or unofficial agent config.
+/// Missing or empty required fields fail with a JSON error string: `{"missing": [...]}`.
+#[tauri::command]
+fn import_device_pack(app: tauri::AppHandle, json: String, which: Option<String>) -> Result<serde_json::Value, String> {
+  const REQUIRED: [&str; 4] = ["api_base_url", "company_id", "device_id", "device_token"];
+
+  let pack: serde_json::Value = serde_json::from_str(json.trim())
+    .map_err(|e| format!("invalid device pack JSON: {e}"))?;
+  let pack = pack
+    .as_object()
+    .ok_or_else(|| "device pack must be a JSON object".to_string())?;
+
+  let mut patch = serde_json::Map::new();
+  let mut missing: Vec<&str> = vec![];
+  for key in REQUIRED {
+    match pack.get(key).and_then(|v| v.as_str()).map(|v| v.trim()) {
+      Some(v) if !v.is_empty() => {
+        patch.insert(key.to_string(), serde_json::Value::String(v.to_string()));
+      }
+      _ => missing.push(key),
+    }
+  }
+  if !missing.is_empty() {
+    return Err(serde_json::json!({ "missing": missing }).to_string());
+  }
+
+  let which = which.unwrap_or_else(|| "official".to_string());
+  let path = profile_dir(&app, &which)?.join("config.json");
+  patch_config_file(&path, &patch)?;
+  Ok(serde_json::json!({
+    "ok": true,
+    "which": which.trim(),
+    "config_path": path.to_string_lossy(),
+  }))
+}
+
+#[tauri::command]
+fn sidecar_version(app: tauri::AppHandle) -> Result<serde_json::Value, String> {
+  let protocol = sidecar_protocol(&app)?;
+  Ok(serde_json::json!({
+    "desktop_version": env!("CARGO_PKG_VERSION"),
+    "sidecar_protocol": protocol,
+    "min_sidecar_protocol": MIN_SIDECAR_PROTOCOL,
+    "compatible": protocol >= MIN_SIDECAR_PROTOCOL,
+  }))
+}
+
+/// Split `http(s)://host[:port][/prefix]` into (https, authority, host, port, prefix).
+fn parse_base_url(url: &str) -> Result<(bool, String, String, u16, String), String> {
+  let url = url.trim().trim_end_matches('/');
+  let (https, rest) = if let Some(r) = url.strip_prefix("https://") {
+    (true, r)
+  } else if let Some(r) = url.strip_prefix("http://") {
+    (false, r)
+  } else {
+    return Err(format!("edge URL must start with http:// or https:// (got {url})"));
+  };
+  let (authority, prefix) = match rest.find('/') {
+    Some(i) => (&rest[..i], rest[i..].to_string()),
+    None => (rest, String::new()),
+  };
+  // IPv6 literals are bracketed: [::1]:8001
+  let (host, port) = match authority.strip_prefix('[').and_then(|a| a.split_once(']')) {
+    Some((h, rest)) => (h, rest.strip_prefix(':')),
+    None => match authority.rsplit_once(':') {
+      Some((h, p)) => (h, Some(p)),
+      None => (authority, None),
+    },
+  };
+  if host.is_empty() {
+    return Err("edge URL has no host".to_string());
+  }
+  let port = match port {
+    Some(p) => p.parse::<u16>().map_err(|_| format!("invalid port in edge URL: {p}"))?,
+    None => if https { 443 } else { 80 },
+  };
+  Ok((https, authority.to_string(), host.to_string(), port, prefix))
+}
+
+/// Resolve on a helper thread: `to_socket_addrs` has no timeout and can hang for
+/// many seconds on a broken resolver.
+fn resolve_with_timeout(host: &str, port: u16, timeout: Duration) -> Result<SocketAddr, String> {
+  let (tx, rx) = std::sync::mpsc::channel();
+  let target = host.to_string();
+  std::thread::spawn(move || {
+    let res = (target.as_str(), port)
+      .to_socket_addrs()
+      .map(|mut it| it.next());
+    let _ = tx.send(res);
+  });
+  match rx.recv_timeout(timeout) {
+    Ok(Ok(Some(addr))) => Ok(addr),
+    Ok(Ok(None)) => Err(format!("could not resolve {host}")),
+    Ok(Err(e)) => Err(format!("could not resolve {host}: {e}")),
+    Err(_) => Err(format!("resolving {host} timed out")),
+  }
+}
+
+/// Probe `<edge_url>/health` and report `{reachable, status, latency_ms, error}`.
+/// Only plain-HTTP (LAN) edges are supported: there is no TLS stack here, and a probe
+/// that can't read the status would leave the online badge unresolved forever.
+#[tauri::command]
+fn check_edge_connectivity(edge_url: String) -> Result<serde_json::Value, String> {
+  let (https, authority, host, port, prefix) = parse_base_url(&edge_url)?;
+  if https {
+    return Err("HTTPS edge URLs can't be probed from POS Desktop; use the edge's http:// LAN URL".to_string());
+  }
+  let offline = |error: String| {
+    serde_json::json!({
+      "reachable": false,
+      "status": null,
+      "latency_ms": null,
+      "error": error,
+    })
+  };
+
+  let started = Instant::now();
+  let addr = match resolve_with_timeout(&host, port, EDGE_PROBE_TIMEOUT) {
+    Ok(a) => a,
+    Err(e) => return Ok(offline(e)),
+  };
+  let remaining = EDGE_PROBE_TIMEOUT
+    .saturating_sub(started.elapsed())
+    .max(Duration::from_millis(100));
+  let mut stream = match TcpStream::connect_timeout(&addr, remaining) {
+    Ok(v) => v,
+    Err(e) => return Ok(offline(format!("connect to {host}:{port} failed: {e}"))),
+  };
+  let _ = stream.set_read_timeout(Some(EDGE_PROBE_TIMEOUT));
+  let _ = stream.set_write_timeout(Some(EDGE_PROBE_TIMEOUT));
+  let req = format!("GET {prefix}/health HTTP/1.1\r\nHost: {authority}\r\nConnection: close\r\n\r\n");
+  if let Err(e) = stream.write_all(req.as_bytes()) {
+    return Ok(offline(format!("request to {host}:{port} failed: {e}")));
+  }
+  let mut buf = [0u8; 256];
+  let n = match stream.read(&mut buf) {
+    Ok(v) if v > 0 => v,
+    Ok(_) => return Ok(offline("empty response from edge".to_string())),
+    Err(e) => return Ok(offline(format!("no response from {host}:{port}: {e}"))),
+  };
+  let latency_ms = started.elapsed().as_millis() as u64;
+  let head = String::from_utf8_lossy(&buf[..n]);
+  let status = head
+    .lines()
+    .next()
+    .and_then(|l| l.split_whitespace().nth(1))
+    .and_then(|c| c.parse::<u16>().ok());
+  Ok(serde_json::json!({
+    "reachable": matches!(status, Some(200..=299)),
+    "status": status,
+    "latency_ms": latency_ms,
+    "error": match status {
+      Some(200..=299) => None,
+      Some(code) => Some(format!("edge health returned HTTP {code}")),
+      None => Some("invalid HTTP response from edge".to_string()),
+    },
+  }))
+}
+
 #[tauri::command]
 fn app_version() -> String {
   env!("CARGO_PKG_VERSION").to_string()
@@ -600,11 +1712,23 @@ fn main() {
     .invoke_handler(tauri::generate_handler![
       start_agents,
       stop_agents,
+      restart_agent,
+      agent_status,
+      agent_db_stats,
+      vacuum_agent_db,
+      reset_profile,
       tail_agent_logs,
+      start_log_stream,
+      stop_log_stream,
       frontend_log,
       tail_desktop_log,
       suggest_port_pair,
+      check_edge_connectivity,
+      patch_config,
+      restore_config,
+      import_device_pack,
       app_version,
+      sidecar_version,
       show_main_window,
       restart_app
     ])
@@ -324,8 +324,12 @@ async function startAgentsWithPortRecovery(portOfficial, portUnofficial) {
   const maxRetries = 6;
   for (let attempt = 0; attempt <= maxRetries; attempt++) {
     try {
-      await tauriInvoke("start_agents", { portOfficial: off, portUnofficial: un });
-      return { portOfficial: off, portUnofficial: un };
+      const started = await tauriInvoke("start_agents", { portOfficial: off, portUnofficial: un });
+      // The desktop shell may move off ports held by other processes; trust what it bound.
+      return {
+        portOfficial: Number(started?.port_official) || off,
+        portUnofficial: Number(started?.port_unofficial) || un,
+      };
     } catch (e) {
       const msg = e instanceof Error ? e.message : String(e);
       if (!isPortConflict(msg) || attempt >= maxRetries) throw e;
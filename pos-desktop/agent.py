@@ -112,6 +112,11 @@ ROOT = os.path.dirname(os.path.abspath(__file__))
 DB_PATH = os.path.join(ROOT, 'pos.sqlite')  # can be overridden via CLI/env (see main())
 CONFIG_PATH = os.path.join(ROOT, 'config.json')  # can be overridden via CLI/env (see main())
 _config_lock = threading.RLock()
+# Bumped when the desktop <-> agent contract (CLI flags, endpoints) changes.
+# POS Desktop refuses to spawn a sidecar older than its minimum (see `--version`).
+AGENT_PROTOCOL_VERSION = 3
+# Set in main() so /api/shutdown can stop the serve_forever loop.
+_HTTP_SERVER = None
 
 # When packaged as a single binary (PyInstaller), data files are extracted under
 # sys._MEIPASS. Keep runtime paths working in both dev + packaged modes.
@@ -767,6 +772,40 @@ def init_db():
         conn.commit()
 
 
+def vacuum_db():
+    # Fold the WAL back into the main file first so VACUUM reclaims everything.
+    conn = sqlite3.connect(DB_PATH)
+    try:
+        conn.execute("PRAGMA busy_timeout=5000")
+        conn.execute("PRAGMA wal_checkpoint(TRUNCATE)")
+        conn.execute("VACUUM")
+    finally:
+        conn.close()
+
+
+def db_stats():
+    conn = sqlite3.connect(DB_PATH)
+    try:
+        cur = conn.cursor()
+        cur.execute("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
+        tables = {}
+        for (name,) in cur.fetchall():
+            # Table names come from sqlite_master, not user input.
+            cur.execute(f'SELECT COUNT(*) FROM "{name}"')
+            tables[name] = cur.fetchone()[0]
+        page_size = cur.execute("PRAGMA page_size").fetchone()[0]
+        page_count = cur.execute("PRAGMA page_count").fetchone()[0]
+        freelist_count = cur.execute("PRAGMA freelist_count").fetchone()[0]
+    finally:
+        conn.close()
+    return {
+        "tables": tables,
+        "page_size": page_size,
+        "page_count": page_count,
+        "freelist_bytes": page_size * freelist_count,
+    }
+
+
 def json_response(handler, payload, status=200):
     body = json.dumps(payload).encode('utf-8')
     handler.send_response(status)
@@ -4326,6 +4365,17 @@ class Handler(BaseHTTPRequestHandler):
             self._handle_cloud_proxy(parsed, method="POST")
             return
 
+        # Graceful stop requested by POS Desktop (loopback-only, never from a browser origin).
+        if parsed.path == "/api/shutdown":
+            if not _is_loopback(client_ip) or (self.headers.get("Origin") or "").strip():
+                json_response(self, {"error": "forbidden"}, status=403)
+                return
+            json_response(self, {"ok": True})
+            if _HTTP_SERVER is not None:
+                # shutdown() blocks until serve_forever exits, so it can't run on this handler thread.
+                threading.Thread(target=_HTTP_SERVER.shutdown, daemon=True).start()
+            return
+
         # Local admin PIN setup (loopback-only).
         if parsed.path == "/api/admin/pin/set":
             if not _is_loopback(client_ip):
@@ -6147,10 +6197,44 @@ class Handler(BaseHTTPRequestHandler):
         json_response(self, {'error': 'not found'}, status=404)
 
 
+# Must stay below POS Desktop's AGENT_SHUTDOWN_GRACE (5s) so we exit before being killed.
+SHUTDOWN_DRAIN_TIMEOUT_SEC = 3.0
+
+
+class AgentHTTPServer(ThreadingHTTPServer):
+    """Tracks in-flight requests so shutdown can let them finish.
+
+    Handler threads are daemons, so server_close() doesn't join them and the
+    interpreter would kill one mid-SQLite-write on exit.
+    """
+
+    def __init__(self, *args, **kwargs):
+        super().__init__(*args, **kwargs)
+        self._inflight = 0
+        self._inflight_cv = threading.Condition()
+
+    def process_request_thread(self, request, client_address):
+        with self._inflight_cv:
+            self._inflight += 1
+        try:
+            super().process_request_thread(request, client_address)
+        finally:
+            with self._inflight_cv:
+                self._inflight -= 1
+                self._inflight_cv.notify_all()
+
+    def wait_for_inflight(self, timeout):
+        with self._inflight_cv:
+            return self._inflight_cv.wait_for(lambda: self._inflight == 0, timeout=timeout)
+
+
 def main():
-    global DB_PATH, CONFIG_PATH
+    global DB_PATH, CONFIG_PATH, _HTTP_SERVER
     parser = argparse.ArgumentParser()
     parser.add_argument("--init-db", action="store_true", help="Initialize local SQLite schema and exit")
+    parser.add_argument("--version", action="store_true", help="Print the agent protocol version and exit")
+    parser.add_argument("--vacuum-db", action="store_true", help="VACUUM the local SQLite DB and exit (agent must be stopped)")
+    parser.add_argument("--db-stats", action="store_true", help="Print local SQLite table row counts as JSON and exit")
     parser.add_argument(
         "--db",
         default=os.environ.get("POS_DB_PATH", DB_PATH),
@@ -6169,6 +6253,10 @@ def main():
     parser.add_argument("--port", type=int, default=int(os.environ.get("POS_PORT", "7070")), help="HTTP port (default: 7070)")
     args = parser.parse_args()
 
+    if args.version:
+        print(AGENT_PROTOCOL_VERSION)
+        return
+
     # Override module-level paths so the rest of the agent uses the selected files.
     # This is intentionally global because helpers read DB_PATH/CONFIG_PATH directly.
     DB_PATH = os.path.abspath(args.db)
@@ -6179,6 +6267,15 @@ def main():
         print("ok")
         return
 
+    if args.vacuum_db:
+        vacuum_db()
+        print("ok")
+        return
+
+    if args.db_stats:
+        print(json.dumps(db_stats()))
+        return
+
     init_db()
     # Clean up expired sessions from previous runs
     try:
@@ -6189,11 +6286,15 @@ def main():
         conn.close()
     except Exception:
         pass
-    server = ThreadingHTTPServer((args.host, args.port), Handler)
+    server = AgentHTTPServer((args.host, args.port), Handler)
     # Print localhost for convenience when bound locally; otherwise print the explicit host.
     public_host = "localhost" if args.host in {"127.0.0.1", "localhost"} else args.host
     print(f"POS Agent running on http://{public_host}:{args.port}")
+    _HTTP_SERVER = server
     server.serve_forever()
+    server.server_close()
+    if not server.wait_for_inflight(SHUTDOWN_DRAIN_TIMEOUT_SEC):
+        _agent_logger.warning("shutdown: in-flight requests did not finish within %.0fs", SHUTDOWN_DRAIN_TIMEOUT_SEC)
 
 
 if __name__ == "__main__":